@@ -1,4 +1,5 @@
-use std::io::{self, Read, Write};
+use alloc::vec::Vec;
+use alloc::string::String;
 
 #[derive(Clone, Debug, Default)]
 pub struct Ast {
@@ -29,11 +30,18 @@ pub enum Error {
     #[fail(display="Found unbalanced right bracket at {}", index)]
     UnbalancedRightBracket { index: usize },
 
+    #[fail(display="Pointer out of bounds: {}", addr)]
+    PointerOutOfBounds { addr: usize },
+
+    #[fail(display="Exceeded step limit after {} instructions", executed)]
+    StepLimitExceeded { executed: u64 },
+
+    #[cfg(feature = "std")]
     #[fail(display="IO error: {}", inner)]
-    Io { inner: io::Error },
+    Io { inner: ::std::io::Error },
 }
 
-pub type Result<T=(), E=Error> = ::std::result::Result<T, E>;
+pub type Result<T=(), E=Error> = ::core::result::Result<T, E>;
 
 pub fn parse(source: &str) -> Result<Ast> {
     #[derive(Default)]
@@ -146,8 +154,9 @@ pub fn parse(source: &str) -> Result<Ast> {
     }
 }
 
-pub fn interpret(ast: &Ast) -> Result {
-    use std::io::{stdin, stdout, StdinLock, StdoutLock};
+#[cfg(feature = "std")]
+pub fn interpret(ast: &Ast, max_steps: Option<u64>) -> Result {
+    use std::io::{stdin, stdout, Read, Write, StdinLock, StdoutLock};
 
     const MEMSIZE: usize = 32 * 1024;
 
@@ -156,33 +165,68 @@ pub fn interpret(ast: &Ast) -> Result {
         p: Address,
         stdin: StdinLock<'a>,
         stdout: StdoutLock<'a>,
+        steps_left: Option<u64>,
+        executed: u64,
     }
 
     impl<'a> Env<'a> {
+        fn tick(&mut self) -> Result {
+            if let Some(steps_left) = self.steps_left {
+                if steps_left == 0 {
+                    return Err(Error::StepLimitExceeded { executed: self.executed });
+                }
+
+                self.steps_left = Some(steps_left - 1);
+            }
+
+            self.executed += 1;
+
+            Ok(())
+        }
+
         fn eval(&mut self, op: &Op) -> Result {
+            self.tick()?;
+
             match op {
-                Op::Add(n) => self.mem[self.p.0] += *n,
+                Op::Add(n) => {
+                    let addr = self.p.checked(self.mem.len())?;
+                    self.mem[addr] += *n;
+                },
 
                 Op::Go(n) => self.p += *n,
 
-                Op::Set(n) => self.mem[self.p.0] = *n,
+                Op::Set(n) => {
+                    let addr = self.p.checked(self.mem.len())?;
+                    self.mem[addr] = *n;
+                },
+
+                Op::Loop(ast) => loop {
+                    self.tick()?;
+
+                    let addr = self.p.checked(self.mem.len())?;
+                    if self.mem[addr].0 == 0 {
+                        break;
+                    }
 
-                Op::Loop(ast) => while self.mem[self.p.0].0 != 0 {
                     for op in &ast.body {
                         self.eval(op)?;
                     }
                 },
 
                 Op::Read => {
-                    let Env { mem, p, stdin, .. } = self;
+                    let addr = self.p.checked(self.mem.len())?;
+                    let Env { mem, stdin, .. } = self;
                     let mut buf: [u8; 1] = [0];
-                    stdin.read(&mut buf)?;
-                    mem[p.0] = Byte(buf[0]);
+                    // EOF (a zero-length read) sets the cell to 0 rather
+                    // than erroring, matching the usual Brainfuck convention.
+                    let n = stdin.read(&mut buf)?;
+                    mem[addr] = Byte(if n == 0 { 0 } else { buf[0] });
                 },
 
                 Op::Write => {
-                    let Byte(c) = self.mem[self.p.0];
-                    self.stdout.write(&[c])?;
+                    let addr = self.p.checked(self.mem.len())?;
+                    let Byte(c) = self.mem[addr];
+                    self.stdout.write_all(&[c])?;
                 },
             }
 
@@ -197,7 +241,7 @@ pub fn interpret(ast: &Ast) -> Result {
     let mem = vec![Byte(0); MEMSIZE];
     let p = Address(0);
 
-    let mut env = Env { mem, p, stdin, stdout };
+    let mut env = Env { mem, p, stdin, stdout, steps_left: max_steps, executed: 0 };
 
     for op in &ast.body {
         env.eval(op)?;
@@ -206,15 +250,16 @@ pub fn interpret(ast: &Ast) -> Result {
     Ok(())
 }
 
-impl From<io::Error> for Error {
-    fn from(inner: io::Error) -> Self {
+#[cfg(feature = "std")]
+impl From<::std::io::Error> for Error {
+    fn from(inner: ::std::io::Error) -> Self {
         Error::Io { inner }
     }
 }
 
 impl Ast {
     pub fn into_c(self) -> String {
-        use std::fmt::{self, Write};
+        use core::fmt::{self, Write};
 
         struct C {
             buf: String,
@@ -291,12 +336,38 @@ impl Ast {
 }
 
 impl Byte {
+    pub fn new(n: u8) -> Self {
+        Byte(n)
+    }
+
     pub fn as_i32(self) -> i32 {
         self.0 as i32
     }
+
+    pub fn as_u8(self) -> u8 {
+        self.0
+    }
+}
+
+impl Address {
+    pub fn as_usize(self) -> usize {
+        self.0
+    }
+
+    /// Resolve this address against a tape of length `len`, or trap with
+    /// `PointerOutOfBounds` if it would leave the tape. `Address` wraps at
+    /// a fixed 64KiB, which can exceed a smaller tape, so every access
+    /// goes through this check instead of indexing directly.
+    pub fn checked(self, len: usize) -> Result<usize> {
+        if self.0 < len {
+            Ok(self.0)
+        } else {
+            Err(Error::PointerOutOfBounds { addr: self.0 })
+        }
+    }
 }
 
-use std::ops::*;
+use core::ops::*;
 
 impl Add<Self> for Byte {
     type Output = Byte;
@@ -0,0 +1,99 @@
+//! Annotated listing of an optimized `Ast`, for debugging the peephole
+//! optimizer's rewrites (e.g. seeing that `[-]` collapsed into `Set(0)`
+//! without reading the generated C).
+
+use core::fmt::Write;
+
+use alloc::format;
+use alloc::string::String;
+
+use crate::bf::{Ast, Op};
+
+/// Render `ast` as one line per op, each prefixed with its position.
+/// Loop bodies are indented (after the position) under their `LOOP`
+/// header, e.g.:
+///
+/// ```text
+/// 0003  ADD +4
+/// 0004  LOOP ->0011
+/// 0005    SET 0
+/// ...
+/// 0011  SET 0
+/// ```
+pub fn disassemble(ast: &Ast) -> String {
+    let mut out = String::new();
+    let mut pos = 0;
+    print_body(ast, 0, &mut pos, &mut out);
+    out
+}
+
+/// Number of positions `ast` occupies in the listing: one per op, with
+/// `Loop` counting its own header plus its body (the listing has no line
+/// for a loop's end).
+fn width(ast: &Ast) -> usize {
+    ast.body.iter().map(|op| match op {
+        Op::Loop(body) => 1 + width(body),
+        _ => 1,
+    }).sum()
+}
+
+fn print_body(ast: &Ast, indent: usize, pos: &mut usize, out: &mut String) {
+    for op in &ast.body {
+        match op {
+            Op::Loop(body) => {
+                let target = *pos + 1 + width(body);
+                line(out, *pos, indent, &format!("LOOP ->{:04}", target));
+                *pos += 1;
+
+                print_body(body, indent + 1, pos, out);
+            },
+
+            other => {
+                line(out, *pos, indent, &opcode_text(other));
+                *pos += 1;
+            },
+        }
+    }
+}
+
+fn opcode_text(op: &Op) -> String {
+    match op {
+        Op::Add(n) => format!("ADD {:+}", n),
+        Op::Go(n) => format!("GO {:+}", n),
+        Op::Set(n) => format!("SET {}", n.as_u8()),
+        Op::Read => String::from("READ"),
+        Op::Write => String::from("WRITE"),
+        Op::Loop(_) => unreachable!("loops are handled in print_body"),
+    }
+}
+
+fn line(out: &mut String, pos: usize, indent: usize, text: &str) {
+    let _ = write!(out, "{:04}  ", pos);
+
+    for _ in 0 .. indent {
+        out.push_str("  ");
+    }
+
+    out.push_str(text);
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bf;
+
+    #[test]
+    fn disassemble_formats_positions_and_loop_targets() {
+        let ast = bf::parse("+[>]").unwrap();
+
+        let expected = concat!(
+            "0000  ADD +1\n",
+            "0001  LOOP ->0003\n",
+            "0002    GO +1\n",
+        );
+
+        assert_eq!(disassemble(&ast), expected);
+    }
+}
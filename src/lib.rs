@@ -0,0 +1,29 @@
+//! Parser, peephole optimizer, and code generators for brainwasm.
+//!
+//! The `bf` module and the bytecode `vm` it feeds compile under
+//! `#![no_std]` (using only `alloc`), so they can be embedded in
+//! environments without a `std`. Enable the `std` feature (on by
+//! default) for the tree-walking interpreter and VM execution loop,
+//! both of which need `std::io` for stdin/stdout. The WebAssembly
+//! backend lives behind its own `wasm` feature, and the `disasm`
+//! feature adds an annotated-listing view of the optimized `Ast`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[macro_use]
+extern crate failure_derive;
+extern crate failure;
+
+#[cfg(feature = "wasm")]
+extern crate parity_wasm;
+
+pub mod bf;
+pub mod vm;
+
+#[cfg(feature = "disasm")]
+pub mod disasm;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,59 +1,94 @@
-#[macro_use]
+#[cfg(feature = "std")]
 extern crate structopt;
 
-#[macro_use]
-extern crate failure_derive;
-extern crate failure;
+extern crate brainwasm;
 
-extern crate parity_wasm;
+#[cfg(feature = "std")]
+mod cli {
+    use std::path::PathBuf;
+    use std::fs::File;
+    use std::io::Write;
 
-pub mod bf;
-pub mod wasm;
+    use structopt::StructOpt;
 
-use std::path::PathBuf;
-use std::fs::File;
-use std::io::Write;
+    use brainwasm::bf;
+    use brainwasm::vm;
 
-use structopt::StructOpt;
+    #[derive(Debug, StructOpt)]
+    #[structopt(name="brainwasm")]
+    pub struct Opt {
+        #[structopt(short="c", long="compile")]
+        compile: bool,
 
-#[derive(Debug, StructOpt)]
-#[structopt(name="brainwasm")]
-struct Opt {
-    #[structopt(short="c", long="compile")]
-    compile: bool,
+        #[structopt(parse(from_os_str))]
+        infile: PathBuf,
 
-    #[structopt(parse(from_os_str))]
-    infile: PathBuf,
+        #[structopt(short="o", parse(from_os_str))]
+        outfile: Option<PathBuf>,
 
-    #[structopt(short="o", parse(from_os_str))]
-    outfile: Option<PathBuf>,
-}
+        #[cfg(feature = "disasm")]
+        #[structopt(long="disasm")]
+        disasm: bool,
 
-fn main() {
-    let opt = Opt::from_args();
+        #[structopt(long="max-steps")]
+        max_steps: Option<u64>,
+
+        /// Run the flattened bytecode VM instead of the tree-walking
+        /// interpreter.
+        #[structopt(long="vm")]
+        vm: bool,
+    }
 
-    let source = std::fs::read_to_string(&opt.infile).unwrap_or_else(|err| {
-        panic!("Can't read {}: {}", opt.infile.display(), err);
-    });
+    pub fn main() {
+        let opt = Opt::from_args();
 
-    let ast = bf::parse(&source).unwrap_or_else(|err| {
-        panic!("{}", err);
-    });
+        let source = std::fs::read_to_string(&opt.infile).unwrap_or_else(|err| {
+            panic!("Can't read {}: {}", opt.infile.display(), err);
+        });
 
-    if !opt.compile && opt.outfile.is_none() {
-        bf::interpret(&ast).unwrap_or_else(|err| {
+        let ast = bf::parse(&source).unwrap_or_else(|err| {
             panic!("{}", err);
         });
 
-        return;
-    }
+        #[cfg(feature = "disasm")]
+        {
+            if opt.disasm {
+                print!("{}", brainwasm::disasm::disassemble(&ast));
+                return;
+            }
+        }
+
+        if !opt.compile && opt.outfile.is_none() {
+            let result = if opt.vm {
+                vm::run(&ast, opt.max_steps)
+            } else {
+                bf::interpret(&ast, opt.max_steps)
+            };
+
+            result.unwrap_or_else(|err| {
+                panic!("{}", err);
+            });
 
-    let c = ast.into_c();
+            return;
+        }
 
-    if let Some(outpath) = &opt.outfile {
-        let mut outfile = File::create(outpath).unwrap();
-        writeln!(outfile, "{}", c).unwrap();
-    } else {
-        println!("{}", c);
+        let c = ast.into_c();
+
+        if let Some(outpath) = &opt.outfile {
+            let mut outfile = File::create(outpath).unwrap();
+            writeln!(outfile, "{}", c).unwrap();
+        } else {
+            println!("{}", c);
+        }
     }
 }
+
+#[cfg(feature = "std")]
+fn main() {
+    cli::main();
+}
+
+#[cfg(not(feature = "std"))]
+fn main() {
+    panic!("the brainwasm CLI requires the `std` feature");
+}
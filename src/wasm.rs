@@ -1,9 +1,11 @@
-use parity_wasm::elements::Instruction as Instr;
+use parity_wasm::builder;
+use parity_wasm::elements::{BlockType, Instruction as Instr, Instructions, ValueType};
 
-use bf::{Ast, Op};
+use crate::bf::{Ast, Op};
 
+#[derive(Debug)]
 pub enum Error {
-
+    Encode(parity_wasm::elements::Error),
 }
 
 pub type Result<T=(), E=Error> = ::std::result::Result<T, E>;
@@ -27,11 +29,18 @@ struct Builder {
 const MEM: u32 = 0;
 const P: u32 = 1;
 
+// Host imports occupy the lowest indices in the function index space,
+// ahead of `main`, in the order they're declared in `finish()`.
+const GETCHAR: u32 = 0;
+const PUTCHAR: u32 = 1;
+const MAIN: u32 = 2;
+
 impl Builder {
     fn translate(&mut self, ast: Ast) -> Result {
         for op in ast.body {
             match op {
                 Op::Add(n) => {
+                    self.push_address()?;
                     self.read_tape()?;
                     self.emit(Instr::I32Const(n as i32))?;
                     self.emit(Instr::I32Add)?;
@@ -46,30 +55,40 @@ impl Builder {
                 },
 
                 Op::Set(n) => {
+                    self.push_address()?;
                     self.emit(Instr::I32Const(n.as_i32()))?;
                     self.write_tape()?;
                 },
 
                 Op::Loop(body) => {
-                    use parity_wasm::elements::BlockType;
-
-                    // FIXME: Branch to end if zero
+                    // An outer `block` we can branch out of when the cell
+                    // is zero, wrapping an inner `loop` we branch back to
+                    // the top of when it isn't.
+                    self.emit(Instr::Block(BlockType::NoResult))?;
                     self.emit(Instr::Loop(BlockType::NoResult))?;
 
+                    self.read_tape()?;
+                    self.emit(Instr::I32Eqz)?;
+                    self.emit(Instr::BrIf(1))?;
+
                     self.translate(body)?;
 
-                    // FIXME: Branch to loop if nonzero
-                    self.emit(Instr::End)?;
+                    self.read_tape()?;
+                    self.emit(Instr::BrIf(0))?;
+
+                    self.emit(Instr::End)?; // loop
+                    self.emit(Instr::End)?; // block
                 },
 
                 Op::Read => {
-                    // FIXME: Read input
+                    self.push_address()?;
+                    self.emit(Instr::Call(GETCHAR))?;
                     self.write_tape()?;
                 },
 
                 Op::Write => {
                     self.read_tape()?;
-                    // FIXME: Write output
+                    self.emit(Instr::Call(PUTCHAR))?;
                 },
             }
         }
@@ -89,17 +108,22 @@ impl Builder {
         self.emit(Instr::SetGlobal(P))
     }
 
-    fn read_tape(&mut self) -> Result {
+    // `i32.store` pops its value from the top of the stack and the
+    // address below that, so the address must be pushed *before* the
+    // value being stored. Callers push the address with this, then
+    // compute/push the value, then finish with `write_tape`.
+    fn push_address(&mut self) -> Result {
         self.read_mem()?;
         self.read_ptr()?;
-        self.emit(Instr::I32Add)?;
+        self.emit(Instr::I32Add)
+    }
+
+    fn read_tape(&mut self) -> Result {
+        self.push_address()?;
         self.emit(Instr::I32Load8U(0, 0))
     }
 
     fn write_tape(&mut self) -> Result {
-        self.read_mem()?;
-        self.read_ptr()?;
-        self.emit(Instr::I32Add)?;
         self.emit(Instr::I32Store8(0, 0))
     }
 
@@ -110,8 +134,85 @@ impl Builder {
     }
 
     fn finish(self) -> Result<Vec<u8>> {
-        // FIXME: Module prelude etc.
-        // FIXME: Actually encode thing
-        Ok(vec![])
+        let mut body = self.buf;
+        body.push(Instr::End);
+
+        let mut module = builder::module();
+
+        // Imports are resolved by type index, so the signatures have to
+        // be registered in the type section before they can be referenced.
+        let getchar_ty = module.push_signature(
+            builder::signature()
+                .with_return_type(Some(ValueType::I32))
+                .build_sig(),
+        );
+
+        let putchar_ty = module.push_signature(
+            builder::signature()
+                .with_param(ValueType::I32)
+                .build_sig(),
+        );
+
+        let module = module
+            .import()
+                .module("env")
+                .field("getchar")
+                .external().func(getchar_ty)
+                .build()
+            .import()
+                .module("env")
+                .field("putchar")
+                .external().func(putchar_ty)
+                .build()
+            .global()
+                .with_type(ValueType::I32) // MEM: data base
+                .mutable()
+                .init_expr(Instr::I32Const(0))
+                .build()
+            .global()
+                .with_type(ValueType::I32) // P: tape pointer
+                .mutable()
+                .init_expr(Instr::I32Const(0))
+                .build()
+            .memory()
+                .with_min(1)
+                .build()
+            .function()
+                .signature().build()
+                .body()
+                    .with_instructions(Instructions::new(body))
+                    .build()
+                .build()
+            .export()
+                .field("main")
+                .internal().func(MAIN)
+                .build()
+            .build();
+
+        Ok(parity_wasm::serialize(module)?)
+    }
+}
+
+impl From<parity_wasm::elements::Error> for Error {
+    fn from(inner: parity_wasm::elements::Error) -> Self {
+        Error::Encode(inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bf;
+
+    #[test]
+    fn compiled_module_round_trips_through_parity_wasm() {
+        let ast = bf::parse("+[>+<-].").unwrap();
+        let bytes = ast.into_wasm().unwrap();
+
+        // Parses back as a structurally valid module and exports `main`.
+        let module = parity_wasm::deserialize_buffer::<parity_wasm::elements::Module>(&bytes)
+            .expect("compiled module should be well-formed wasm");
+
+        let exports = module.export_section().expect("export section");
+        assert!(exports.entries().iter().any(|e| e.field() == "main"));
     }
 }